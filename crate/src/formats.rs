@@ -0,0 +1,502 @@
+//! Encoding a `PhotonImage` into bytes in a chosen output format.
+//!
+//! `PhotonImage::get_base64` used to hard-code PNG output. This module adds an
+//! [`OutputFormat`] selector and an encode path so callers can ask for JPEG, WebP,
+//! BMP, TIFF, or GIF instead, trading the losslessness of PNG for a smaller payload.
+//!
+//! PNG output also has its own tuning knobs, [`PngOptions`], since the scanline
+//! filter chosen ahead of deflate has a big effect on the final file size.
+//!
+//! This only covers the in-memory encode path (`get_bytes_with_format`/
+//! `get_base64_with_format`). A format-aware counterpart to native file save
+//! would belong in the `native` module (which already owns `open_image`), not
+//! here, and isn't added in this change.
+
+use crate::helpers;
+use crate::metadata;
+use crate::quantize;
+use crate::PhotonImage;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::gif::GifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::tiff::TiffEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ColorType, ImageEncoder};
+use std::io::{Cursor, Write};
+
+/// The image format to encode a `PhotonImage` into.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Lossless PNG.
+    Png(PngOptions),
+    /// Lossy JPEG, with a quality between 1 and 100.
+    Jpeg(u8),
+    /// WebP.
+    WebP,
+    /// Bitmap.
+    Bmp,
+    /// TIFF.
+    Tiff,
+    /// GIF.
+    Gif,
+    /// Indexed (palette) PNG, quantized down to the given number of colors.
+    ///
+    /// The palette only stores RGB; source alpha is discarded, so any
+    /// partial transparency becomes fully opaque (no `tRNS` chunk is written).
+    Indexed(u16),
+}
+
+impl OutputFormat {
+    /// The MIME type to use for a `data:` URI encoded in this format.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Png(_) => "image/png",
+            OutputFormat::Jpeg(_) => "image/jpeg",
+            OutputFormat::WebP => "image/webp",
+            OutputFormat::Bmp => "image/bmp",
+            OutputFormat::Tiff => "image/tiff",
+            OutputFormat::Gif => "image/gif",
+            OutputFormat::Indexed(_) => "image/png",
+        }
+    }
+}
+
+/// Per-scanline filter picked before a PNG row is handed to deflate.
+///
+/// Each of `Sub`, `Up`, `Average`, and `Paeth` predicts a pixel from its
+/// neighbours and stores the (signed) residual, which deflate then compresses.
+/// `MinSum` and `Entropy` pick, per row, whichever of the five candidate
+/// filters scores best by that metric, at the cost of computing all five.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FilterStrategy {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+    /// Minimize the sum of `|filtered byte|` over the row, treating bytes as signed.
+    MinSum,
+    /// Minimize the Shannon entropy of the row's filtered byte distribution.
+    Entropy,
+}
+
+/// Tuning knobs for PNG encoding.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PngOptions {
+    pub filter_strategy: FilterStrategy,
+    /// zlib compression level, 0 (none) to 9 (best).
+    pub compression: u8,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        PngOptions {
+            filter_strategy: FilterStrategy::MinSum,
+            compression: 6,
+        }
+    }
+}
+
+/// Encode a `PhotonImage` to bytes in the given format.
+pub fn encode(img: &PhotonImage, format: OutputFormat) -> Vec<u8> {
+    let dyn_img = helpers::dyn_image_from_raw(img);
+    let width = img.get_width();
+    let height = img.get_height();
+
+    let mut buffer = Vec::new();
+    match format {
+        OutputFormat::Png(opts) => {
+            buffer = encode_png(img, opts);
+        }
+        OutputFormat::Jpeg(quality) => {
+            // JPEG has no alpha channel, so composite onto an opaque white
+            // background first instead of just dropping the alpha byte.
+            let rgb = flatten_onto_white(&dyn_img.to_rgba8());
+            JpegEncoder::new_with_quality(&mut buffer, quality)
+                .write_image(&rgb, width, height, ColorType::Rgb8)
+                .unwrap();
+        }
+        OutputFormat::WebP => {
+            // Pure-Rust lossless encoder: avoids a native libwebp dependency,
+            // which matters since this crate also targets wasm.
+            let rgba = dyn_img.to_rgba8();
+            WebPEncoder::new_lossless(&mut buffer)
+                .write_image(&rgba, width, height, ColorType::Rgba8)
+                .unwrap();
+        }
+        OutputFormat::Bmp => {
+            let rgba = dyn_img.to_rgba8();
+            BmpEncoder::new(&mut buffer)
+                .write_image(&rgba, width, height, ColorType::Rgba8)
+                .unwrap();
+        }
+        OutputFormat::Tiff => {
+            let rgba = dyn_img.to_rgba8();
+            // TiffEncoder needs Seek, which a bare Vec<u8> doesn't implement.
+            TiffEncoder::new(Cursor::new(&mut buffer))
+                .write_image(&rgba, width, height, ColorType::Rgba8)
+                .unwrap();
+        }
+        OutputFormat::Gif => {
+            let rgba = dyn_img.to_rgba8();
+            GifEncoder::new(&mut buffer)
+                .encode(&rgba, width, height, ColorType::Rgba8)
+                .unwrap();
+        }
+        OutputFormat::Indexed(num_colors) => {
+            buffer = encode_indexed_png(img, num_colors);
+        }
+    }
+    buffer
+}
+
+const BYTES_PER_PIXEL: usize = 4; // RGBA8
+
+/// Encode a `PhotonImage` to PNG, picking a scanline filter per `opts.filter_strategy`
+/// and deflating at `opts.compression` ourselves, so we can support heuristics (`MinSum`,
+/// `Entropy`) that aren't exposed by a one-shot encoder call.
+fn encode_png(img: &PhotonImage, opts: PngOptions) -> Vec<u8> {
+    let width = img.get_width();
+    let height = img.get_height();
+    let rgba = helpers::dyn_image_from_raw(img).to_rgba8();
+    let raw = rgba.as_raw();
+    let stride = width as usize * BYTES_PER_PIXEL;
+
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    let zero_row = vec![0u8; stride];
+    let mut prev_row: &[u8] = &zero_row;
+    for y in 0..height as usize {
+        let row = &raw[y * stride..(y + 1) * stride];
+        let (filter_type, filtered_row) =
+            filter_row(row, prev_row, BYTES_PER_PIXEL, opts.filter_strategy);
+        filtered.push(filter_type);
+        filtered.extend_from_slice(&filtered_row);
+        prev_row = row;
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(opts.compression.min(9) as u32));
+    encoder.write_all(&filtered).unwrap();
+    let idat_data = encoder.finish().unwrap();
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit depth, RGBA colour type, default compression/filter/interlace
+
+    let mut png = Vec::with_capacity(8 + idat_data.len() + 64);
+    png.extend_from_slice(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+    png.extend_from_slice(&metadata::chunk_bytes(b"IHDR", &ihdr));
+    png.extend_from_slice(&metadata::chunk_bytes(b"IDAT", &idat_data));
+    png.extend_from_slice(&metadata::encode_text_chunks(&img.metadata));
+    png.extend_from_slice(&metadata::chunk_bytes(b"IEND", &[]));
+    png
+}
+
+/// Filter one scanline per `strategy`, returning the PNG filter-type byte used and the
+/// filtered row bytes. `bpp` is the number of bytes per pixel (4 for RGBA8, 1 for indexed).
+fn filter_row(row: &[u8], prev_row: &[u8], bpp: usize, strategy: FilterStrategy) -> (u8, Vec<u8>) {
+    match strategy {
+        FilterStrategy::None => (0, apply_filter(0, row, prev_row, bpp)),
+        FilterStrategy::Sub => (1, apply_filter(1, row, prev_row, bpp)),
+        FilterStrategy::Up => (2, apply_filter(2, row, prev_row, bpp)),
+        FilterStrategy::Average => (3, apply_filter(3, row, prev_row, bpp)),
+        FilterStrategy::Paeth => (4, apply_filter(4, row, prev_row, bpp)),
+        FilterStrategy::MinSum => best_filter_by(row, prev_row, bpp, |filtered| {
+            filtered.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum()
+        }),
+        FilterStrategy::Entropy => best_filter_by(row, prev_row, bpp, |filtered| {
+            (shannon_entropy(filtered) * 1_000_000.0) as u64
+        }),
+    }
+}
+
+/// Compute all five filter candidates for a row and keep the one that minimizes `score`.
+fn best_filter_by(
+    row: &[u8],
+    prev_row: &[u8],
+    bpp: usize,
+    score: impl Fn(&[u8]) -> u64,
+) -> (u8, Vec<u8>) {
+    (0..=4)
+        .map(|filter_type| {
+            let filtered = apply_filter(filter_type, row, prev_row, bpp);
+            let s = score(&filtered);
+            (filter_type, filtered, s)
+        })
+        .min_by_key(|(_, _, s)| *s)
+        .map(|(filter_type, filtered, _)| (filter_type, filtered))
+        .unwrap()
+}
+
+fn apply_filter(filter_type: u8, row: &[u8], prev_row: &[u8], bpp: usize) -> Vec<u8> {
+    row.iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = prev_row[i];
+            let c = if i >= bpp { prev_row[i - bpp] } else { 0 };
+            match filter_type {
+                0 => x,
+                1 => x.wrapping_sub(a),
+                2 => x.wrapping_sub(b),
+                3 => x.wrapping_sub(((a as u16 + b as u16) / 2) as u8),
+                4 => x.wrapping_sub(paeth_predictor(a, b, c)),
+                _ => unreachable!("PNG filter types are 0-4"),
+            }
+        })
+        .collect()
+}
+
+fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+    let (a, b, c) = (a as i16, b as i16, c as i16);
+    let p = a + b - c;
+    let pa = (p - a).abs();
+    let pb = (p - b).abs();
+    let pc = (p - c).abs();
+    if pa <= pb && pa <= pc {
+        a as u8
+    } else if pb <= pc {
+        b as u8
+    } else {
+        c as u8
+    }
+}
+
+/// Encode `img` as an indexed (palette) PNG: quantize it down to `num_colors` via
+/// median-cut, then write one index byte per pixel plus a `PLTE` chunk instead of
+/// four RGBA bytes per pixel.
+///
+/// The palette (and `extract_palette`/`quantize` underneath it) is RGB-only, so
+/// source alpha is dropped here: no `tRNS` chunk is written, and every pixel comes
+/// back fully opaque regardless of its original alpha.
+fn encode_indexed_png(img: &PhotonImage, num_colors: u16) -> Vec<u8> {
+    assert!(
+        num_colors > 0 && num_colors <= 256,
+        "indexed PNG supports palettes of 1 to 256 colors, got {}",
+        num_colors
+    );
+
+    let width = img.get_width();
+    let height = img.get_height();
+
+    let palette = quantize::extract_palette(img, num_colors);
+    let indices = quantize::index_pixels(img, &palette);
+
+    let stride = width as usize;
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    for y in 0..height as usize {
+        let row = &indices[y * stride..(y + 1) * stride];
+        // Indexed bytes aren't spatially correlated the way colour channels are,
+        // so per-pixel prediction rarely helps: skip straight to the `None` filter.
+        filtered.push(0u8);
+        filtered.extend_from_slice(row);
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&filtered).unwrap();
+    let idat_data = encoder.finish().unwrap();
+
+    let mut plte = Vec::with_capacity(palette.len() * 3);
+    for color in &palette {
+        plte.push(color.get_red());
+        plte.push(color.get_green());
+        plte.push(color.get_blue());
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 3, 0, 0, 0]); // 8-bit depth, indexed colour type
+
+    let mut png = Vec::with_capacity(8 + idat_data.len() + plte.len() + 64);
+    png.extend_from_slice(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+    png.extend_from_slice(&metadata::chunk_bytes(b"IHDR", &ihdr));
+    png.extend_from_slice(&metadata::chunk_bytes(b"PLTE", &plte));
+    png.extend_from_slice(&metadata::chunk_bytes(b"IDAT", &idat_data));
+    png.extend_from_slice(&metadata::encode_text_chunks(&img.metadata));
+    png.extend_from_slice(&metadata::chunk_bytes(b"IEND", &[]));
+    png
+}
+
+/// Composite an RGBA image onto an opaque white background, for formats (like
+/// JPEG) that have no alpha channel of their own.
+fn flatten_onto_white(rgba: &image::RgbaImage) -> image::RgbImage {
+    image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let alpha = a as f32 / 255.0;
+        let blend = |channel: u8| (channel as f32 * alpha + 255.0 * (1.0 - alpha)).round() as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+fn shannon_entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0u32; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard(width: u32, height: u32) -> PhotonImage {
+        let mut raw_pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let on = (x + y) % 2 == 0;
+                raw_pixels.extend_from_slice(if on {
+                    &[255, 0, 0, 255]
+                } else {
+                    &[0, 255, 0, 128]
+                });
+            }
+        }
+        PhotonImage::new(raw_pixels, width, height)
+    }
+
+    fn all_filter_strategies() -> [FilterStrategy; 7] {
+        [
+            FilterStrategy::None,
+            FilterStrategy::Sub,
+            FilterStrategy::Up,
+            FilterStrategy::Average,
+            FilterStrategy::Paeth,
+            FilterStrategy::MinSum,
+            FilterStrategy::Entropy,
+        ]
+    }
+
+    #[test]
+    fn encode_png_roundtrips_pixels_under_every_filter_strategy() {
+        let img = checkerboard(4, 4);
+        for filter_strategy in all_filter_strategies() {
+            let opts = PngOptions {
+                filter_strategy,
+                compression: 6,
+            };
+            let bytes = encode_png(&img, opts);
+            let decoded = image::load_from_memory(&bytes)
+                .unwrap_or_else(|e| panic!("{:?} produced an undecodable PNG: {}", filter_strategy, e))
+                .to_rgba8();
+            assert_eq!(decoded.as_raw(), &img.get_raw_pixels(), "{:?}", filter_strategy);
+        }
+    }
+
+    #[test]
+    fn apply_filter_and_unfilter_are_inverses() {
+        let row = [10u8, 250, 3, 7, 8, 9];
+        let prev_row = [1u8, 2, 3, 4, 5, 6];
+        let bpp = 2;
+
+        for filter_type in 0..=4u8 {
+            let filtered = apply_filter(filter_type, &row, &prev_row, bpp);
+            let mut reconstructed = vec![0u8; row.len()];
+            for i in 0..row.len() {
+                let a = if i >= bpp { reconstructed[i - bpp] } else { 0 };
+                let b = prev_row[i];
+                let c = if i >= bpp { prev_row[i - bpp] } else { 0 };
+                reconstructed[i] = match filter_type {
+                    0 => filtered[i],
+                    1 => filtered[i].wrapping_add(a),
+                    2 => filtered[i].wrapping_add(b),
+                    3 => filtered[i].wrapping_add(((a as u16 + b as u16) / 2) as u8),
+                    4 => filtered[i].wrapping_add(paeth_predictor(a, b, c)),
+                    _ => unreachable!(),
+                };
+            }
+            assert_eq!(reconstructed, row, "filter type {}", filter_type);
+        }
+    }
+
+    #[test]
+    fn jpeg_composites_alpha_onto_white_instead_of_truncating_it() {
+        // A half-transparent red pixel, composited onto white, should land
+        // roughly halfway between red and white rather than just dropping alpha.
+        let img = PhotonImage::new(vec![255, 0, 0, 128], 1, 1);
+        let bytes = encode(&img, OutputFormat::Jpeg(90));
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        let pixel = decoded.get_pixel(0, 0);
+        assert!(pixel[0] > 150, "red channel should stay high: {:?}", pixel);
+        assert!(pixel[1] > 100, "green channel should rise toward white: {:?}", pixel);
+        assert!(pixel[2] > 100, "blue channel should rise toward white: {:?}", pixel);
+    }
+
+    #[test]
+    fn webp_roundtrips_pixels_losslessly() {
+        let img = checkerboard(4, 4);
+        let bytes = encode(&img, OutputFormat::WebP);
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.as_raw(), &img.get_raw_pixels());
+    }
+
+    #[test]
+    fn bmp_roundtrips_pixels_losslessly() {
+        let img = checkerboard(4, 4);
+        let bytes = encode(&img, OutputFormat::Bmp);
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.as_raw(), &img.get_raw_pixels());
+    }
+
+    #[test]
+    fn tiff_roundtrips_pixels_losslessly() {
+        let img = checkerboard(4, 4);
+        let bytes = encode(&img, OutputFormat::Tiff);
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.as_raw(), &img.get_raw_pixels());
+    }
+
+    #[test]
+    fn gif_encodes_to_the_right_dimensions() {
+        // GIF is palette-limited, so this only checks it decodes at the right
+        // size rather than asserting lossless pixel equality.
+        let img = checkerboard(4, 4);
+        let bytes = encode(&img, OutputFormat::Gif);
+        let decoded = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 4));
+    }
+
+    #[test]
+    fn encode_indexed_png_decodes_to_a_quantized_palette() {
+        // checkerboard alternates opaque red and half-transparent green.
+        let img = checkerboard(4, 4);
+        let bytes = encode_indexed_png(&img, 2);
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!((decoded.width(), decoded.height()), (4, 4));
+
+        for pixel in decoded.pixels() {
+            let [r, g, b, a] = pixel.0;
+            let is_red = (r, g, b) == (255, 0, 0);
+            let is_green = (r, g, b) == (0, 255, 0);
+            assert!(is_red || is_green, "unexpected color {:?}", pixel);
+            // Indexed output has no tRNS chunk, so alpha is always fully
+            // opaque even though the source had a half-transparent color.
+            assert_eq!(a, 255, "indexed PNG should always decode fully opaque");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "1 to 256 colors")]
+    fn encode_indexed_png_rejects_too_many_colors() {
+        let img = checkerboard(2, 2);
+        encode_indexed_png(&img, 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 to 256 colors")]
+    fn encode_indexed_png_rejects_zero_colors() {
+        let img = checkerboard(2, 2);
+        encode_indexed_png(&img, 0);
+    }
+}
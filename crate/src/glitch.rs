@@ -0,0 +1,260 @@
+//! Databending / glitch-art effects that corrupt an image at the level of its
+//! PNG filtered scanlines, rather than its raw RGBA pixels.
+//!
+//! PNG filters are cumulative down the image (`Up`, `Average`, and `Paeth` all
+//! reference the previous row), so a single corrupted byte in the inflated IDAT
+//! stream propagates into the horizontal streaking characteristic of databent
+//! glitch art. We get there by re-encoding the image to PNG, inflating its IDAT
+//! stream to recover the per-row `[filter byte, ...pixel bytes]` layout, corrupting
+//! it, deflating it back, and decoding the result.
+
+use crate::formats::{self, OutputFormat, PngOptions};
+use crate::PhotonImage;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// A splitmix64 PRNG, so `glitch` is reproducible for a given seed without
+/// pulling in an external `rand` dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform `f32` in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    fn next_range(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            0
+        } else {
+            (self.next_u64() as usize) % bound
+        }
+    }
+}
+
+/// Corrupt `img` with a databending glitch effect, in place.
+///
+/// `intensity` (clamped to `0.0..=1.0`) controls how much of the filtered PNG
+/// scanline data is disturbed: higher values flip more data bytes, rewrite more
+/// rows' filter-type bytes, and shift more row offsets. `seed` makes the result
+/// reproducible.
+pub fn glitch(img: &mut PhotonImage, intensity: f32, seed: u64) {
+    let width = img.get_width();
+    let height = img.get_height();
+    let intensity = intensity.clamp(0.0, 1.0);
+
+    let png = formats::encode(img, OutputFormat::Png(PngOptions::default()));
+    let mut filtered = inflate_idat(&png);
+
+    let stride = width as usize * 4 + 1; // filter-type byte + RGBA row
+    let mut rng = Rng::new(seed);
+
+    for row in 0..height as usize {
+        let row_start = row * stride;
+        if row_start >= filtered.len() {
+            break;
+        }
+        let row_end = (row_start + stride).min(filtered.len());
+
+        // (a) flip a fraction of the row's data bytes.
+        for byte in filtered[row_start + 1..row_end].iter_mut() {
+            if rng.next_f32() < intensity * 0.05 {
+                *byte ^= 1 << rng.next_range(8);
+            }
+        }
+
+        // (b) rewrite the row's filter-type byte to a different filter (0-4).
+        if rng.next_f32() < intensity * 0.3 {
+            let current = filtered[row_start] as usize;
+            filtered[row_start] = ((current + 1 + rng.next_range(4)) % 5) as u8;
+        }
+
+        // (c) shift the row's byte offset, rotating its data bytes within the row.
+        if rng.next_f32() < intensity * 0.1 {
+            let len = row_end - (row_start + 1);
+            if len > 0 {
+                let shift = (1 + rng.next_range(4)) % len;
+                filtered[row_start + 1..row_end].rotate_right(shift);
+            }
+        }
+    }
+
+    let new_png = reencode_idat(&png, &filtered);
+    *img = PhotonImage::new_from_byteslice(new_png);
+}
+
+/// Shift the red, green, or blue channel of `img` by `(offset_x, offset_y)` pixels,
+/// for a chromatic-aberration / RGB-split effect.
+pub fn channel_shift(img: &mut PhotonImage, offset_x: i32, offset_y: i32, channel: usize) {
+    assert!(channel < 3, "channel must be 0 (red), 1 (green), or 2 (blue)");
+
+    let width = img.get_width() as i32;
+    let height = img.get_height() as i32;
+    let original = img.get_raw_pixels();
+    let mut shifted = original.clone();
+
+    for y in 0..height {
+        for x in 0..width {
+            let src_x = x - offset_x;
+            let src_y = y - offset_y;
+            if src_x < 0 || src_x >= width || src_y < 0 || src_y >= height {
+                continue;
+            }
+            let dst_index = ((y * width + x) * 4 + channel as i32) as usize;
+            let src_index = ((src_y * width + src_x) * 4 + channel as i32) as usize;
+            shifted[dst_index] = original[src_index];
+        }
+    }
+
+    let metadata = img.metadata.clone();
+    let mut result = PhotonImage::new(shifted, img.get_width(), img.get_height());
+    result.metadata = metadata;
+    *img = result;
+}
+
+/// Concatenate the data of every `IDAT` chunk in a PNG byte stream, and inflate it.
+fn inflate_idat(png: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    let mut offset = 8; // past the PNG signature
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let chunk_type = &png[offset + 4..offset + 8];
+        let data_start = offset + 8;
+        let data_end = data_start + length;
+
+        if chunk_type == b"IDAT" {
+            compressed.extend_from_slice(&png[data_start..data_end]);
+        }
+        offset = data_end + 4;
+    }
+
+    let mut decoder = ZlibDecoder::new(compressed.as_slice());
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out).unwrap();
+    out
+}
+
+/// Re-deflate `filtered` and splice it back into `png` as a single fresh `IDAT` chunk,
+/// dropping any other `IDAT` chunks the source may have had.
+fn reencode_idat(png: &[u8], filtered: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(filtered).unwrap();
+    let idat_data = encoder.finish().unwrap();
+
+    let mut out = Vec::with_capacity(png.len());
+    out.extend_from_slice(&png[..8]); // PNG signature
+
+    let mut offset = 8;
+    let mut idat_written = false;
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let chunk_type = &png[offset + 4..offset + 8];
+        let chunk_end = offset + 8 + length + 4;
+
+        if chunk_type == b"IDAT" {
+            if !idat_written {
+                out.extend_from_slice(&crate::metadata::chunk_bytes(b"IDAT", &idat_data));
+                idat_written = true;
+            }
+        } else {
+            out.extend_from_slice(&png[offset..chunk_end]);
+        }
+        offset = chunk_end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_image(width: u32, height: u32) -> PhotonImage {
+        let mut raw_pixels = Vec::with_capacity((width * height) as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                raw_pixels.extend_from_slice(&[(x % 256) as u8, (y % 256) as u8, 128, 255]);
+            }
+        }
+        PhotonImage::new(raw_pixels, width, height)
+    }
+
+    #[test]
+    fn glitch_preserves_dimensions_and_metadata() {
+        let mut img = gradient_image(16, 16);
+        img.set_metadata("author", "jane");
+
+        glitch(&mut img, 0.5, 42);
+
+        assert_eq!((img.get_width(), img.get_height()), (16, 16));
+        assert_eq!(img.get_metadata("author"), Some(&"jane".to_string()));
+    }
+
+    #[test]
+    fn glitch_is_deterministic_for_a_given_seed() {
+        let mut a = gradient_image(16, 16);
+        let mut b = gradient_image(16, 16);
+
+        glitch(&mut a, 0.8, 7);
+        glitch(&mut b, 0.8, 7);
+
+        assert_eq!(a.get_raw_pixels(), b.get_raw_pixels());
+    }
+
+    #[test]
+    fn glitch_with_zero_intensity_leaves_pixels_unchanged() {
+        let mut img = gradient_image(16, 16);
+        let original = img.get_raw_pixels();
+
+        glitch(&mut img, 0.0, 1);
+
+        assert_eq!(img.get_raw_pixels(), original);
+    }
+
+    #[test]
+    fn channel_shift_preserves_metadata() {
+        let mut img = gradient_image(8, 8);
+        img.set_metadata("author", "jane");
+
+        channel_shift(&mut img, 1, 0, 0);
+
+        assert_eq!(img.get_metadata("author"), Some(&"jane".to_string()));
+    }
+
+    #[test]
+    fn channel_shift_moves_only_the_selected_channel() {
+        let mut img = gradient_image(8, 8);
+        let original = img.get_raw_pixels();
+
+        channel_shift(&mut img, 1, 0, 0);
+
+        let shifted = img.get_raw_pixels();
+        // The red channel moved one pixel right: past column 0, each pixel now
+        // holds its left neighbour's original red value.
+        for y in 0..8usize {
+            for x in 1..8usize {
+                let i = (y * 8 + x) * 4;
+                assert_eq!(shifted[i], original[i - 4]);
+            }
+        }
+        // The green, blue, and alpha channels are untouched by a red-channel shift.
+        for (orig, shifted) in original.chunks_exact(4).zip(shifted.chunks_exact(4)) {
+            assert_eq!(orig[1], shifted[1]);
+            assert_eq!(orig[2], shifted[2]);
+            assert_eq!(orig[3], shifted[3]);
+        }
+    }
+}
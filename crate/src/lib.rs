@@ -43,6 +43,7 @@ use base64::{decode, encode};
 use image::DynamicImage::ImageRgba8;
 use image::{GenericImage, GenericImageView};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -58,6 +59,7 @@ pub struct PhotonImage {
     raw_pixels: Vec<u8>,
     width: u32,
     height: u32,
+    metadata: HashMap<String, String>,
 }
 
 impl PhotonImage {
@@ -67,6 +69,7 @@ impl PhotonImage {
             raw_pixels,
             width,
             height,
+            metadata: HashMap::new(),
         }
     }
 
@@ -87,9 +90,21 @@ impl PhotonImage {
             raw_pixels,
             width: img.width(),
             height: img.height(),
+            metadata: metadata::extract_chunks(slice),
         }
     }
 
+    /// Set a metadata key/value pair (e.g. author, copyright, software) on this image.
+    /// PNG output carries this as tEXt/zTXt/iTXt chunks.
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        self.metadata.insert(key.to_string(), value.to_string());
+    }
+
+    /// Get a metadata value by key, if this image carries one.
+    pub fn get_metadata(&self, key: &str) -> Option<&String> {
+        self.metadata.get(key)
+    }
+
     // pub fn new_from_buffer(buffer: &Buffer, width: u32, height: u32) -> PhotonImage {
     //     // Convert a Node.js Buffer into a Vec<u8>
     //     let raw_pixels: Vec<u8> = Uint8Array::new_with_byte_offset_and_length(
@@ -122,17 +137,24 @@ impl PhotonImage {
 
     /// Convert the PhotonImage to base64.
     pub fn get_base64(&self) -> String {
-        let mut img = helpers::dyn_image_from_raw(self);
-        img = ImageRgba8(img.to_rgba8());
+        self.get_base64_with_format(formats::OutputFormat::Png(formats::PngOptions::default()))
+    }
 
-        let mut buffer = vec![];
-        img.write_to(&mut buffer, image::ImageOutputFormat::Png)
-            .unwrap();
-        let base64 = encode(&buffer);
+    /// Convert the PhotonImage to bytes, encoded in the given format.
+    pub fn get_bytes_with_format(&self, format: formats::OutputFormat) -> Vec<u8> {
+        formats::encode(self, format)
+    }
 
-        let res_base64 = format!("data:image/png;base64,{}", base64.replace("\r\n", ""));
+    /// Convert the PhotonImage to a base64 `data:` URI, encoded in the given format.
+    pub fn get_base64_with_format(&self, format: formats::OutputFormat) -> String {
+        let buffer = self.get_bytes_with_format(format);
+        let base64 = encode(&buffer);
 
-        res_base64
+        format!(
+            "data:{};base64,{}",
+            format.mime_type(),
+            base64.replace("\r\n", "")
+        )
     }
 }
 
@@ -193,7 +215,7 @@ impl From<Vec<u8>> for Rgb {
 
 /// RGBA color type.
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct Rgba {
     r: u8,
     g: u8,
@@ -272,6 +294,7 @@ pub fn base64_to_image(base64: &str) -> PhotonImage {
         raw_pixels,
         width: img.width(),
         height: img.height(),
+        metadata: metadata::extract_chunks(slice),
     }
 }
 
@@ -286,12 +309,18 @@ pub mod colour_spaces;
 pub mod conv;
 pub mod effects;
 pub mod filters;
+pub mod formats;
+pub mod glitch;
 pub mod helpers;
 mod iter;
+mod metadata;
 pub mod monochrome;
 pub mod multiple;
 pub mod native;
 pub mod noise;
+pub mod quantize;
+pub mod templates;
 mod tests;
 pub mod text;
+pub mod thumbnail;
 pub mod transform;
@@ -0,0 +1,261 @@
+//! Text metadata (tEXt/zTXt/iTXt) embedded in PNG output, and read back on decode.
+//!
+//! A `PhotonImage` can carry arbitrary key/value text pairs (author, copyright,
+//! software, processing history, ...). These are written into PNG output as
+//! ancillary chunks and parsed back out of PNG input, so metadata survives a
+//! round-trip through `get_base64`/`new_from_byteslice` without relying on
+//! `image`'s lossy `write_to`, which drops ancillary chunks entirely.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Build a full PNG chunk (length + type + data + CRC) from its type and payload.
+pub(crate) fn chunk_bytes(chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let mut body = Vec::with_capacity(data.len() + 4);
+    body.extend_from_slice(chunk_type);
+    body.extend_from_slice(data);
+
+    chunk.extend_from_slice(&body);
+    chunk.extend_from_slice(&crc32(&body).to_be_bytes());
+    chunk
+}
+
+/// Encode a metadata map into a sequence of tEXt/zTXt/iTXt chunks.
+///
+/// Latin-1-safe values are written as `tEXt`, or `zTXt` once they're long enough
+/// that zlib compression is worth the overhead; anything outside Latin-1 is
+/// written as UTF-8 via `iTXt`.
+pub(crate) fn encode_text_chunks(metadata: &HashMap<String, String>) -> Vec<u8> {
+    const ZTXT_THRESHOLD: usize = 128;
+
+    let mut out = Vec::new();
+    for (key, value) in metadata {
+        if let Some(latin1) = to_latin1(value) {
+            if latin1.len() < ZTXT_THRESHOLD {
+                let mut data = key.as_bytes().to_vec();
+                data.push(0);
+                data.extend_from_slice(&latin1);
+                out.extend_from_slice(&chunk_bytes(b"tEXt", &data));
+            } else {
+                let mut data = key.as_bytes().to_vec();
+                data.push(0); // null separator
+                data.push(0); // compression method (0 = zlib)
+                data.extend_from_slice(&zlib_compress(&latin1));
+                out.extend_from_slice(&chunk_bytes(b"zTXt", &data));
+            }
+        } else {
+            let mut data = key.as_bytes().to_vec();
+            data.push(0); // null separator
+            data.push(0); // compression flag (0 = uncompressed)
+            data.push(0); // compression method
+            data.push(0); // language tag (empty)
+            data.push(0); // translated keyword (empty)
+            data.extend_from_slice(value.as_bytes());
+            out.extend_from_slice(&chunk_bytes(b"iTXt", &data));
+        }
+    }
+    out
+}
+
+/// Scan a PNG byte stream and recover any tEXt/zTXt/iTXt metadata it carries.
+pub(crate) fn extract_chunks(png: &[u8]) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    if !png.starts_with(&PNG_SIGNATURE) {
+        return metadata;
+    }
+
+    let mut offset = PNG_SIGNATURE.len();
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let chunk_type = &png[offset + 4..offset + 8];
+        let data_start = offset + 8;
+        let data_end = data_start + length;
+        if data_end + 4 > png.len() {
+            break;
+        }
+        let data = &png[data_start..data_end];
+
+        match chunk_type {
+            b"tEXt" => {
+                if let Some((key, value)) = parse_text_chunk(data) {
+                    metadata.insert(key, value);
+                }
+            }
+            b"zTXt" => {
+                if let Some((key, value)) = parse_ztxt_chunk(data) {
+                    metadata.insert(key, value);
+                }
+            }
+            b"iTXt" => {
+                if let Some((key, value)) = parse_itxt_chunk(data) {
+                    metadata.insert(key, value);
+                }
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+
+        offset = data_end + 4; // skip the trailing CRC
+    }
+    metadata
+}
+
+fn parse_text_chunk(data: &[u8]) -> Option<(String, String)> {
+    let null_pos = data.iter().position(|&b| b == 0)?;
+    let key = from_latin1(&data[..null_pos]);
+    let value = from_latin1(&data[null_pos + 1..]);
+    Some((key, value))
+}
+
+fn parse_ztxt_chunk(data: &[u8]) -> Option<(String, String)> {
+    let null_pos = data.iter().position(|&b| b == 0)?;
+    let key = from_latin1(&data[..null_pos]);
+    let compressed = &data[null_pos + 2..]; // skip null separator + compression method byte
+    let decompressed = zlib_decompress(compressed)?;
+    Some((key, from_latin1(&decompressed)))
+}
+
+fn parse_itxt_chunk(data: &[u8]) -> Option<(String, String)> {
+    let null_pos = data.iter().position(|&b| b == 0)?;
+    let key = String::from_utf8_lossy(&data[..null_pos]).into_owned();
+
+    let compression_flag = *data.get(null_pos + 1)?;
+    let rest = &data[null_pos + 3..]; // skip compression flag + compression method
+
+    let lang_end = rest.iter().position(|&b| b == 0)?;
+    let translated_start = lang_end + 1;
+    let translated_end = rest[translated_start..].iter().position(|&b| b == 0)? + translated_start;
+    let text = &rest[translated_end + 1..];
+
+    let value = if compression_flag == 1 {
+        String::from_utf8_lossy(&zlib_decompress(text)?).into_owned()
+    } else {
+        String::from_utf8_lossy(text).into_owned()
+    };
+    Some((key, value))
+}
+
+fn to_latin1(value: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for c in value.chars() {
+        if (c as u32) > 0xFF {
+            return None;
+        }
+        bytes.push(c as u8);
+    }
+    Some(bytes)
+}
+
+fn from_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zlib_decompress(data: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Read;
+    let mut decoder = ZlibDecoder::new(data);
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out).ok()?;
+    Some(out)
+}
+
+/// CRC-32 (zlib/PNG variant) used to checksum every PNG chunk.
+fn crc32(bytes: &[u8]) -> u32 {
+    static TABLE: [u32; 256] = build_crc_table();
+
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        let index = ((crc ^ byte as u32) & 0xFF) as usize;
+        crc = TABLE[index] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+const fn build_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(metadata: HashMap<String, String>) -> HashMap<String, String> {
+        let chunks = encode_text_chunks(&metadata);
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&chunk_bytes(b"IHDR", &[0u8; 13]));
+        png.extend_from_slice(&chunks);
+        png.extend_from_slice(&chunk_bytes(b"IEND", &[]));
+
+        extract_chunks(&png)
+    }
+
+    #[test]
+    fn roundtrips_short_latin1_value_as_text() {
+        let mut metadata = HashMap::new();
+        metadata.insert("Author".to_string(), "Jane Doe".to_string());
+
+        let recovered = roundtrip(metadata.clone());
+        assert_eq!(recovered, metadata);
+    }
+
+    #[test]
+    fn roundtrips_long_latin1_value_as_compressed_text() {
+        let mut metadata = HashMap::new();
+        metadata.insert("History".to_string(), "a".repeat(200));
+
+        let recovered = roundtrip(metadata.clone());
+        assert_eq!(recovered, metadata);
+    }
+
+    #[test]
+    fn roundtrips_non_latin1_value_as_international_text() {
+        let mut metadata = HashMap::new();
+        metadata.insert("Title".to_string(), "naïve café 日本語".to_string());
+
+        let recovered = roundtrip(metadata.clone());
+        assert_eq!(recovered, metadata);
+    }
+
+    #[test]
+    fn extract_chunks_on_non_png_data_is_empty() {
+        let recovered = extract_chunks(b"not a png");
+        assert!(recovered.is_empty());
+    }
+
+    #[test]
+    fn crc32_matches_known_value() {
+        // From the PNG spec example: CRC of an IEND chunk type+data (empty data).
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+}
@@ -0,0 +1,205 @@
+//! Indexed/palette colour support via median-cut quantization.
+//!
+//! `quantize` reduces a `PhotonImage`'s colours down to an N-entry palette, for
+//! small indexed-PNG output and retro/poster effects. `extract_palette` exposes
+//! the computed palette without mutating the image.
+
+use crate::{PhotonImage, Rgb};
+
+/// A bounding box of pixels in RGB space, as used by median-cut quantization.
+struct ColorBox {
+    pixels: Vec<[u8; 3]>,
+}
+
+impl ColorBox {
+    /// The channel (0=R, 1=G, 2=B) with the largest extent in this box, and that extent.
+    fn widest_channel(&self) -> (usize, u8) {
+        let mut mins = [u8::MAX; 3];
+        let mut maxs = [u8::MIN; 3];
+        for pixel in &self.pixels {
+            for c in 0..3 {
+                mins[c] = mins[c].min(pixel[c]);
+                maxs[c] = maxs[c].max(pixel[c]);
+            }
+        }
+        let ranges = [maxs[0] - mins[0], maxs[1] - mins[1], maxs[2] - mins[2]];
+        (0..3).max_by_key(|&c| ranges[c]).map(|c| (c, ranges[c])).unwrap()
+    }
+
+    /// The average colour of the pixels in this box.
+    fn average(&self) -> Rgb {
+        let mut sum = [0u64; 3];
+        for pixel in &self.pixels {
+            for c in 0..3 {
+                sum[c] += pixel[c] as u64;
+            }
+        }
+        let n = self.pixels.len().max(1) as u64;
+        Rgb::new((sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8)
+    }
+
+    /// Sort along the widest channel and split at the median into two boxes.
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let (channel, _) = self.widest_channel();
+        self.pixels.sort_by_key(|pixel| pixel[channel]);
+        let second_half = self.pixels.split_off(self.pixels.len() / 2);
+        (
+            ColorBox { pixels: self.pixels },
+            ColorBox { pixels: second_half },
+        )
+    }
+}
+
+/// Compute a palette of at most `num_colors` entries for `img`, via median-cut
+/// quantization: start with one box spanning every pixel, then repeatedly split
+/// whichever splittable box has the largest extent along any channel until the
+/// palette reaches the requested size.
+pub fn extract_palette(img: &PhotonImage, num_colors: u16) -> Vec<Rgb> {
+    let raw_pixels = img.get_raw_pixels();
+    let pixels: Vec<[u8; 3]> = raw_pixels
+        .chunks_exact(4)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+
+    let mut boxes = vec![ColorBox { pixels }];
+    let target = (num_colors.max(1) as usize).min(raw_pixels.len() / 4).max(1);
+
+    while boxes.len() < target {
+        let split_index = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.pixels.len() > 1)
+            .max_by_key(|(_, b)| b.widest_channel().1)
+            .map(|(i, _)| i);
+
+        let index = match split_index {
+            Some(i) => i,
+            None => break,
+        };
+
+        let (a, b) = boxes.remove(index).split();
+        boxes.push(a);
+        boxes.push(b);
+    }
+
+    boxes.iter().map(ColorBox::average).collect()
+}
+
+/// Rewrite `img`'s pixels to the nearest colour (squared-Euclidean, in RGB) in an
+/// `num_colors`-entry median-cut palette.
+pub fn quantize(img: &mut PhotonImage, num_colors: u16) {
+    let palette = extract_palette(img, num_colors);
+    let mut raw_pixels = img.get_raw_pixels();
+
+    for pixel in raw_pixels.chunks_exact_mut(4) {
+        let nearest = nearest_color(&palette, pixel[0], pixel[1], pixel[2]);
+        pixel[0] = nearest.get_red();
+        pixel[1] = nearest.get_green();
+        pixel[2] = nearest.get_blue();
+    }
+
+    let metadata = img.metadata.clone();
+    let mut result = PhotonImage::new(raw_pixels, img.get_width(), img.get_height());
+    result.metadata = metadata;
+    *img = result;
+}
+
+/// Map every pixel of `img` to the index of its nearest entry in `palette`.
+pub(crate) fn index_pixels(img: &PhotonImage, palette: &[Rgb]) -> Vec<u8> {
+    img.get_raw_pixels()
+        .chunks_exact(4)
+        .map(|p| nearest_index(palette, p[0], p[1], p[2]))
+        .collect()
+}
+
+fn nearest_index(palette: &[Rgb], r: u8, g: u8, b: u8) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| squared_distance(c, r, g, b))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0)
+}
+
+fn nearest_color(palette: &[Rgb], r: u8, g: u8, b: u8) -> &Rgb {
+    palette
+        .iter()
+        .min_by_key(|c| squared_distance(c, r, g, b))
+        .unwrap()
+}
+
+fn squared_distance(c: &Rgb, r: u8, g: u8, b: u8) -> i32 {
+    let dr = c.get_red() as i32 - r as i32;
+    let dg = c.get_green() as i32 - g as i32;
+    let db = c.get_blue() as i32 - b as i32;
+    dr * dr + dg * dg + db * db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_color_image() -> PhotonImage {
+        // 4 red pixels, 4 blue pixels: an easy split for median-cut to find.
+        let mut raw_pixels = Vec::new();
+        for _ in 0..4 {
+            raw_pixels.extend_from_slice(&[255, 0, 0, 255]);
+        }
+        for _ in 0..4 {
+            raw_pixels.extend_from_slice(&[0, 0, 255, 255]);
+        }
+        PhotonImage::new(raw_pixels, 4, 2)
+    }
+
+    #[test]
+    fn extract_palette_finds_the_two_distinct_colors() {
+        let img = two_color_image();
+        let palette = extract_palette(&img, 2);
+
+        assert_eq!(palette.len(), 2);
+        let reds = [palette[0].get_red(), palette[1].get_red()];
+        let blues = [palette[0].get_blue(), palette[1].get_blue()];
+        assert!(reds.contains(&255) && reds.contains(&0));
+        assert!(blues.contains(&255) && blues.contains(&0));
+    }
+
+    #[test]
+    fn extract_palette_caps_at_the_pixel_count() {
+        let img = two_color_image(); // 8 pixels total
+        let palette = extract_palette(&img, 1000);
+        assert_eq!(palette.len(), 8);
+    }
+
+    #[test]
+    fn quantize_preserves_metadata() {
+        let mut img = two_color_image();
+        img.set_metadata("author", "jane");
+
+        quantize(&mut img, 2);
+
+        assert_eq!(img.get_metadata("author"), Some(&"jane".to_string()));
+    }
+
+    #[test]
+    fn quantize_maps_every_pixel_to_a_palette_color() {
+        let mut img = two_color_image();
+        quantize(&mut img, 2);
+
+        let pixels = img.get_raw_pixels();
+        for pixel in pixels.chunks_exact(4) {
+            let is_red = pixel[0] == 255 && pixel[1] == 0 && pixel[2] == 0;
+            let is_blue = pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 255;
+            assert!(is_red || is_blue, "unexpected pixel {:?}", pixel);
+        }
+    }
+
+    #[test]
+    fn index_pixels_stays_within_palette_bounds() {
+        let img = two_color_image();
+        let palette = extract_palette(&img, 2);
+        let indices = index_pixels(&img, &palette);
+
+        assert_eq!(indices.len(), 8);
+        assert!(indices.iter().all(|&i| (i as usize) < palette.len()));
+    }
+}
@@ -0,0 +1,161 @@
+//! Presets for common social-media image dimensions, and fitting an image into them.
+//!
+//! `fit_to_preset` resizes (and crops or pads) a `PhotonImage` to a named preset in
+//! one call, instead of manually chaining `transform::resize` and `transform::crop`.
+//! It composes with the text/watermark modules, which can then overlay a caption on
+//! the result.
+
+use crate::transform::{self, SamplingFilter};
+use crate::{PhotonImage, Rgba};
+
+/// A named social-media image preset and its pixel dimensions.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Preset {
+    InstagramSquare,
+    InstagramStory,
+    TwitterHeader,
+    FacebookCover,
+    YouTubeThumbnail,
+}
+
+impl Preset {
+    /// The target `(width, height)` for this preset.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Preset::InstagramSquare => (1080, 1080),
+            Preset::InstagramStory => (1080, 1920),
+            Preset::TwitterHeader => (1500, 500),
+            Preset::FacebookCover => (820, 312),
+            Preset::YouTubeThumbnail => (1280, 720),
+        }
+    }
+}
+
+/// How to fit a source image into a preset's dimensions.
+#[derive(Copy, Clone, Debug)]
+pub enum FitMode {
+    /// Scale to fill the preset, then center-crop any overflow.
+    Cover,
+    /// Scale to fit inside the preset, padding empty space with a background colour.
+    Contain(Rgba),
+    /// Scale both axes independently to match the preset exactly, ignoring aspect ratio.
+    Stretch,
+}
+
+/// Resize (and crop or pad) `img` to fit `preset`, per `fit_mode`.
+pub fn fit_to_preset(img: &PhotonImage, preset: Preset, fit_mode: FitMode) -> PhotonImage {
+    let (target_w, target_h) = preset.dimensions();
+
+    match fit_mode {
+        FitMode::Stretch => transform::resize(img, target_w, target_h, SamplingFilter::Lanczos3),
+        FitMode::Cover => cover(img, target_w, target_h),
+        FitMode::Contain(background) => contain(img, target_w, target_h, background),
+    }
+}
+
+/// Crop `img` down to the center sub-box matching `target_w`x`target_h`'s aspect
+/// ratio, then scale that sub-box up to `target_w`x`target_h`.
+///
+/// Cropping in source space first (instead of scaling the whole source up to
+/// cover the target and cropping the overflow afterwards) keeps every
+/// intermediate buffer bounded by the source or target size. For an
+/// extreme-aspect-ratio source, scaling first can blow the intermediate up to
+/// gigabytes before the crop ever runs.
+fn cover(img: &PhotonImage, target_w: u32, target_h: u32) -> PhotonImage {
+    let (src_w, src_h) = (img.get_width(), img.get_height());
+    let target_aspect = target_w as f64 / target_h as f64;
+    let src_aspect = src_w as f64 / src_h as f64;
+
+    let (crop_w, crop_h) = if src_aspect > target_aspect {
+        // Source is relatively wider than the target: crop its width down.
+        let crop_w = ((src_h as f64 * target_aspect).round() as u32).min(src_w);
+        (crop_w, src_h)
+    } else {
+        // Source is relatively taller than the target: crop its height down.
+        let crop_h = ((src_w as f64 / target_aspect).round() as u32).min(src_h);
+        (src_w, crop_h)
+    };
+
+    let x1 = (src_w - crop_w) / 2;
+    let y1 = (src_h - crop_h) / 2;
+
+    let mut cropped = img.clone();
+    let cropped = transform::crop(&mut cropped, x1, y1, x1 + crop_w, y1 + crop_h);
+    transform::resize(&cropped, target_w, target_h, SamplingFilter::Lanczos3)
+}
+
+/// Scale `img` (up or down) to fit inside `target_w`x`target_h`, padding the remainder with `background`.
+fn contain(img: &PhotonImage, target_w: u32, target_h: u32, background: Rgba) -> PhotonImage {
+    let scale = (target_w as f64 / img.get_width() as f64)
+        .min(target_h as f64 / img.get_height() as f64);
+    let scaled_w = (img.get_width() as f64 * scale).round() as u32;
+    let scaled_h = (img.get_height() as f64 * scale).round() as u32;
+
+    let resized = transform::resize(img, scaled_w, scaled_h, SamplingFilter::Lanczos3);
+
+    let mut canvas = vec![0u8; (target_w * target_h) as usize * 4];
+    for pixel in canvas.chunks_exact_mut(4) {
+        pixel[0] = background.get_red();
+        pixel[1] = background.get_green();
+        pixel[2] = background.get_blue();
+        pixel[3] = background.get_alpha();
+    }
+
+    let offset_x = (target_w - scaled_w) / 2;
+    let offset_y = (target_h - scaled_h) / 2;
+    let resized_pixels = resized.get_raw_pixels();
+    let row_bytes = (scaled_w * 4) as usize;
+    for y in 0..scaled_h {
+        let src_start = (y * scaled_w * 4) as usize;
+        let src_row = &resized_pixels[src_start..src_start + row_bytes];
+        let dst_start = (((y + offset_y) * target_w + offset_x) * 4) as usize;
+        canvas[dst_start..dst_start + row_bytes].copy_from_slice(src_row);
+    }
+
+    PhotonImage::new(canvas, target_w, target_h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_image(width: u32, height: u32) -> PhotonImage {
+        PhotonImage::new(vec![200u8; (width * height) as usize * 4], width, height)
+    }
+
+    #[test]
+    fn stretch_matches_preset_dimensions_exactly() {
+        let img = solid_image(100, 400);
+        let out = fit_to_preset(&img, Preset::InstagramSquare, FitMode::Stretch);
+        assert_eq!((out.get_width(), out.get_height()), (1080, 1080));
+    }
+
+    #[test]
+    fn cover_matches_preset_dimensions_for_an_extreme_aspect_ratio_source() {
+        // A 999x1 source used to force a ~4.7GB intermediate buffer before cropping;
+        // this should complete instantly and just produce the target size.
+        let img = solid_image(999, 1);
+        let out = fit_to_preset(&img, Preset::InstagramSquare, FitMode::Cover);
+        assert_eq!((out.get_width(), out.get_height()), (1080, 1080));
+    }
+
+    #[test]
+    fn cover_matches_preset_dimensions_for_a_normal_source() {
+        let img = solid_image(400, 300);
+        let out = fit_to_preset(&img, Preset::TwitterHeader, FitMode::Cover);
+        assert_eq!((out.get_width(), out.get_height()), (1500, 500));
+    }
+
+    #[test]
+    fn contain_matches_preset_dimensions_and_pads_with_background() {
+        let img = solid_image(100, 100);
+        let background = Rgba::new(10, 20, 30, 255);
+        let out = fit_to_preset(&img, Preset::TwitterHeader, FitMode::Contain(background));
+        assert_eq!((out.get_width(), out.get_height()), (1500, 500));
+
+        // A square source fit into a wide preset pads the top-left corner with
+        // the background color.
+        let pixels = out.get_raw_pixels();
+        assert_eq!(&pixels[0..4], &[10, 20, 30, 255]);
+    }
+}
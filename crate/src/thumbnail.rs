@@ -0,0 +1,63 @@
+//! Multi-size thumbnail generation, parallelized across sizes.
+//!
+//! `generate_thumbnails` produces several downscaled versions of one source image
+//! in a single call, so a batch pipeline can build a responsive-image set
+//! (1x/2x/thumb/preview) from a single decode instead of repeated open/resize
+//! round-trips. Parallelism is gated behind the `parallel` feature, since `rayon`
+//! isn't available on wasm.
+
+use crate::transform::{self, SamplingFilter};
+use crate::PhotonImage;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Generate a downscaled `PhotonImage` for each `(width, height)` in `sizes`.
+///
+/// With the `parallel` feature enabled, the independent resize jobs are spread
+/// across rayon's thread pool; otherwise they run sequentially.
+pub fn generate_thumbnails(img: &PhotonImage, sizes: &[(u32, u32)]) -> Vec<PhotonImage> {
+    #[cfg(feature = "parallel")]
+    {
+        sizes
+            .par_iter()
+            .map(|&(width, height)| {
+                transform::resize(img, width, height, SamplingFilter::Lanczos3)
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        sizes
+            .iter()
+            .map(|&(width, height)| {
+                transform::resize(img, width, height, SamplingFilter::Lanczos3)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_one_thumbnail_per_size() {
+        let img = PhotonImage::new(vec![128u8; 100 * 100 * 4], 100, 100);
+        let sizes = [(50, 50), (32, 32), (16, 16)];
+
+        let thumbnails = generate_thumbnails(&img, &sizes);
+
+        assert_eq!(thumbnails.len(), sizes.len());
+        for (thumbnail, &(width, height)) in thumbnails.iter().zip(sizes.iter()) {
+            assert_eq!((thumbnail.get_width(), thumbnail.get_height()), (width, height));
+        }
+    }
+
+    #[test]
+    fn empty_sizes_produces_no_thumbnails() {
+        let img = PhotonImage::new(vec![128u8; 10 * 10 * 4], 10, 10);
+        assert!(generate_thumbnails(&img, &[]).is_empty());
+    }
+}